@@ -0,0 +1,181 @@
+use anyhow::{anyhow, Result};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+/// How a [`RewriteRule`] decides whether it applies to a message.
+#[derive(Debug, Clone)]
+pub enum Matcher {
+    /// Matches when the message contains this substring.
+    Substr(String),
+    /// Matches when this regex matches anywhere in the message.
+    Regex(Regex),
+}
+
+impl Matcher {
+    fn is_match(&self, msg: &str) -> bool {
+        match self {
+            Matcher::Substr(s) => msg.contains(s.as_str()),
+            Matcher::Regex(re) => re.is_match(msg),
+        }
+    }
+}
+
+/// A whole-message rewrite: when `matcher` matches, the message is replaced
+/// outright with `replace_with`. Spec format: `substr:<text> => <canonical>` or
+/// `regex:<pattern> => <canonical>`.
+#[derive(Debug, Clone)]
+pub struct RewriteRule {
+    pub matcher: Matcher,
+    pub replace_with: String,
+}
+
+impl FromStr for RewriteRule {
+    type Err = anyhow::Error;
+
+    fn from_str(spec: &str) -> Result<Self> {
+        let (pattern, replace_with) = spec
+            .split_once(" => ")
+            .ok_or_else(|| anyhow!("rewrite rule missing ` => ` separator: {:?}", spec))?;
+        let matcher = match pattern.split_once(':') {
+            Some(("substr", text)) => Matcher::Substr(text.to_string()),
+            Some(("regex", re)) => Matcher::Regex(Regex::new(re)?),
+            _ => return Err(anyhow!("rewrite rule needs a `substr:`/`regex:` prefix: {:?}", spec)),
+        };
+        Ok(Self {
+            matcher,
+            replace_with: replace_with.to_string(),
+        })
+    }
+}
+
+/// A capture-based substitution applied in place, mirroring the `local`/`module`/
+/// `type`/`Some(...)` patterns. Spec format: `<regex> => <replacement>`, where
+/// the replacement may reference capture groups (`$1`).
+#[derive(Debug, Clone)]
+pub struct SubstitutionRule {
+    pub pattern: Regex,
+    pub replace_with: String,
+}
+
+impl FromStr for SubstitutionRule {
+    type Err = anyhow::Error;
+
+    fn from_str(spec: &str) -> Result<Self> {
+        let (pattern, replace_with) = spec
+            .split_once(" => ")
+            .ok_or_else(|| anyhow!("substitution rule missing ` => ` separator: {:?}", spec))?;
+        Ok(Self {
+            pattern: Regex::new(pattern)?,
+            replace_with: replace_with.to_string(),
+        })
+    }
+}
+
+/// An ordered, data-driven canonicalization table: the first matching
+/// [`RewriteRule`] wins; if none match, every [`SubstitutionRule`] is applied in
+/// order. Parsing from string specs keeps the rules editable via config without
+/// recompiling.
+#[derive(Debug, Clone)]
+pub struct CanonicalizationRules {
+    pub rewrites: Vec<RewriteRule>,
+    pub substitutions: Vec<SubstitutionRule>,
+}
+
+impl CanonicalizationRules {
+    /// Parse rule tables from string specs.
+    pub fn from_specs(rewrites: &[&str], substitutions: &[&str]) -> Result<Self> {
+        Ok(Self {
+            rewrites: rewrites.iter().map(|s| s.parse()).collect::<Result<_>>()?,
+            substitutions: substitutions.iter().map(|s| s.parse()).collect::<Result<_>>()?,
+        })
+    }
+
+    /// Canonicalize `msg` by applying the first matching rewrite, or otherwise
+    /// every substitution in order.
+    pub fn apply(&self, msg: &str) -> String {
+        for rule in self.rewrites.iter() {
+            if rule.matcher.is_match(msg) {
+                return rule.replace_with.clone();
+            }
+        }
+        let mut out = msg.to_string();
+        for rule in self.substitutions.iter() {
+            out = rule
+                .pattern
+                .replace_all(&out, rule.replace_with.as_str())
+                .to_string();
+        }
+        out
+    }
+}
+
+/// The rewrite/substitution specs move-smith ships with when a [`Manifest`]
+/// doesn't override them.
+///
+/// [`Manifest`]: super::manifest::Manifest
+pub fn default_specs() -> (&'static [&'static str], &'static [&'static str]) {
+    (
+        &[
+            "substr:mutable ownership violated => ...cannot copy while mutably borrowed...",
+            "substr:which is still mutably borrowed => ...cannot copy while mutably borrowed...",
+            "substr:cannot extract resource => ...cannot acquire...",
+            "substr:function acquires global => ...cannot acquire...",
+            "substr:cannot infer type => ...cannot infer type...",
+            "substr:unable to infer instantiation of type => ...cannot infer type...",
+        ],
+        &[
+            r"local\s+`[^`]+` => [some variable]",
+            r"module\s+'[^']+' => [some module]",
+            r"type\s+`[^`]+` => [some type]",
+            r"Some\([^\)]+\) => [some value]",
+        ],
+    )
+}
+
+/// The compiled form of [`default_specs`], built once and reused by both the
+/// runner's own default and [`Manifest`]s with no `[canonicalization]`
+/// section, instead of recompiling the same regexes on every lookup.
+///
+/// [`Manifest`]: super::manifest::Manifest
+static DEFAULT_RULES: Lazy<CanonicalizationRules> = Lazy::new(|| {
+    let (rewrites, substitutions) = default_specs();
+    CanonicalizationRules::from_specs(rewrites, substitutions)
+        .expect("default canonicalization rules are well-formed")
+});
+
+/// The canonicalization table move-smith ships with, compiled once.
+pub fn default_rules() -> &'static CanonicalizationRules {
+    &DEFAULT_RULES
+}
+
+/// A serializable, TOML-friendly mirror of [`CanonicalizationRules`]: plain
+/// spec strings rather than compiled matchers, so a [`Manifest`] can carry a
+/// `[canonicalization]` section and override the built-in rule table without
+/// recompiling.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct CanonicalizationSpec {
+    #[serde(default)]
+    pub rewrites: Vec<String>,
+    #[serde(default)]
+    pub substitutions: Vec<String>,
+}
+
+impl CanonicalizationSpec {
+    /// Compile this spec into a [`CanonicalizationRules`] table.
+    pub fn to_rules(&self) -> Result<CanonicalizationRules> {
+        let rewrites: Vec<&str> = self.rewrites.iter().map(String::as_str).collect();
+        let substitutions: Vec<&str> = self.substitutions.iter().map(String::as_str).collect();
+        CanonicalizationRules::from_specs(&rewrites, &substitutions)
+    }
+
+    /// The spec for move-smith's built-in rule table.
+    pub fn default_spec() -> Self {
+        let (rewrites, substitutions) = default_specs();
+        Self {
+            rewrites: rewrites.iter().map(|s| s.to_string()).collect(),
+            substitutions: substitutions.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+}
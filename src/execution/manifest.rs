@@ -0,0 +1,117 @@
+use super::canonicalize::{self, CanonicalizationRules, CanonicalizationSpec};
+use anyhow::Result;
+#[cfg(feature = "git_deps")]
+use move_model::metadata::LanguageVersion;
+#[cfg(feature = "local_deps")]
+use move_model_local::metadata::LanguageVersion;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, path::PathBuf, str::FromStr};
+
+/// A single named compiler profile: one language version plus a set of V2
+/// experiment flags. A flag is enabled unless it is suffixed with `=off`
+/// (or `=false`, or `=0`), mirroring the `name=value` form used elsewhere in the
+/// config layer.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Profile {
+    #[serde(default = "default_language_version")]
+    pub language_version: String,
+    #[serde(default)]
+    pub experiments: Vec<String>,
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        Self {
+            language_version: default_language_version(),
+            experiments: vec![],
+        }
+    }
+}
+
+fn default_language_version() -> String {
+    "2.0".to_string()
+}
+
+impl Profile {
+    /// Parse `language_version` into a typed [`LanguageVersion`], falling back to
+    /// `V2_0` for unrecognized strings.
+    pub fn to_language_version(&self) -> LanguageVersion {
+        LanguageVersion::from_str(&self.language_version).unwrap_or(LanguageVersion::V2_0)
+    }
+
+    /// Expand the flag list into the `(name, enabled)` pairs the VM harness
+    /// expects for `v2_experiments`.
+    pub fn to_experiments(&self) -> Vec<(String, bool)> {
+        self.experiments
+            .iter()
+            .map(|flag| match flag.split_once('=') {
+                Some((name, value)) => {
+                    let enabled = !matches!(value.trim(), "off" | "false" | "0");
+                    (name.trim().to_string(), enabled)
+                },
+                None => (flag.trim().to_string(), true),
+            })
+            .collect()
+    }
+}
+
+/// A layered, named-environment manifest: a table of profiles plus a matrix of
+/// profile pairs to cross-compare. Deserialized from TOML via serde.
+///
+/// ```toml
+/// matrix = [["optimized", "no_opt"]]
+///
+/// [profile.optimized]
+/// language_version = "2.0"
+/// experiments = ["optimize"]
+///
+/// [profile.no_opt]
+/// language_version = "2.0"
+/// experiments = ["optimize=off"]
+///
+/// [canonicalization]
+/// rewrites = ["substr:cannot extract resource => ...cannot acquire..."]
+/// substitutions = ["local\\s+`[^`]+` => [some variable]"]
+/// ```
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct Manifest {
+    #[serde(default, rename = "profile")]
+    pub profiles: BTreeMap<String, Profile>,
+    #[serde(default)]
+    pub matrix: Vec<[String; 2]>,
+    /// Overrides the built-in canonicalization rule table (see
+    /// [`CanonicalizationSpec`]). Falls back to
+    /// [`CanonicalizationSpec::default_spec`] when absent, so existing
+    /// manifests without a `[canonicalization]` section keep the built-in
+    /// rules.
+    #[serde(default)]
+    pub canonicalization: Option<CanonicalizationSpec>,
+}
+
+impl Manifest {
+    /// Load and parse a manifest from a TOML file.
+    pub fn from_toml_file(path: &PathBuf) -> Result<Self> {
+        let text = std::fs::read_to_string(path)?;
+        Self::from_toml_str(&text)
+    }
+
+    /// Parse a manifest from a TOML string.
+    pub fn from_toml_str(text: &str) -> Result<Self> {
+        Ok(toml::from_str(text)?)
+    }
+
+    /// Look up a profile by name.
+    pub fn profile(&self, name: &str) -> Option<&Profile> {
+        self.profiles.get(name)
+    }
+
+    /// Compile this manifest's canonicalization rules, falling back to the
+    /// already-compiled built-in table when the manifest has no
+    /// `[canonicalization]` section, rather than recompiling it.
+    pub fn canonicalization_rules(&self) -> Result<CanonicalizationRules> {
+        match &self.canonicalization {
+            Some(spec) => spec.to_rules(),
+            None => Ok(canonicalize::default_rules().clone()),
+        }
+    }
+}
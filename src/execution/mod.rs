@@ -1,3 +1,5 @@
+pub mod canonicalize;
+pub mod manifest;
 pub mod transactional;
 
 use anyhow::{anyhow, Result};
@@ -1,7 +1,11 @@
-use super::Executor;
+use super::{
+    canonicalize::{self, CanonicalizationRules},
+    manifest::Manifest,
+    Executor,
+};
 use crate::{config::CompilerSetting, utils::create_tmp_move_file};
 use anyhow::Result;
-use log::error;
+use log::{error, info};
 #[cfg(feature = "git_deps")]
 use move_model::metadata::LanguageVersion;
 #[cfg(feature = "local_deps")]
@@ -11,20 +15,27 @@ use move_transactional_test_runner::{vm_test_harness, vm_test_harness::TestRunCo
 #[cfg(feature = "local_deps")]
 use move_transactional_test_runner_local::{vm_test_harness, vm_test_harness::TestRunConfig};
 use once_cell::sync::Lazy;
+use rand::{rngs::SmallRng, seq::SliceRandom, SeedableRng};
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 use std::{
     collections::BTreeSet,
     error::Error,
     fmt::Display,
+    hash::{Hash, Hasher},
     panic,
     path::PathBuf,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
     time::{Duration, Instant},
 };
 use tempfile::TempDir;
 
 pub struct TransactionalRunner {
     saved_results: BTreeSet<TransactionalResult>,
+    saved_signatures: BTreeSet<u64>,
 }
 
 pub struct TransactionalInput {
@@ -39,6 +50,10 @@ pub struct TransactionalResult {
     pub v1_chunks: Vec<ResultChunk>,
     pub v2_chunks: Vec<ResultChunk>,
     pub duration: Duration,
+    /// Seed of the [`CorpusRunner`] that produced this result, embedded so a
+    /// failure report can be replayed deterministically. Zero when the result
+    /// was produced outside a seeded corpus run.
+    pub seed: u64,
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
@@ -56,7 +71,7 @@ pub struct ResultChunk {
     pub lines: Vec<String>,
 }
 
-#[derive(Default, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+#[derive(Default, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
 pub enum ResultChunkKind {
     #[default]
     Task,
@@ -109,7 +124,11 @@ impl TransactionalInput {
 }
 
 impl TransactionalResult {
-    pub fn from_run_result(res: &Result<(), Box<dyn Error>>, duration: Duration) -> Self {
+    pub fn from_run_result(
+        res: &Result<(), Box<dyn Error>>,
+        duration: Duration,
+        rules: &CanonicalizationRules,
+    ) -> Self {
         match res {
             Ok(_) => Self {
                 log: "Success".to_string(),
@@ -117,12 +136,13 @@ impl TransactionalResult {
                 v1_chunks: vec![],
                 v2_chunks: vec![],
                 duration,
+                seed: 0,
             },
             Err(e) => {
                 let log = format!("{:?}", e);
                 let (v1_log, v2_log) = Self::split_diff_log(&log);
-                let v1_chunks = ResultChunk::log_to_chunck(v1_log);
-                let v2_chunks = ResultChunk::log_to_chunck(v2_log);
+                let v1_chunks = ResultChunk::log_to_chunck(v1_log, rules);
+                let v2_chunks = ResultChunk::log_to_chunck(v2_log, rules);
                 let status = ResultStatus::check_chunks(&v1_chunks, &v2_chunks);
                 Self {
                     log,
@@ -130,11 +150,33 @@ impl TransactionalResult {
                     v2_chunks,
                     status,
                     duration,
+                    seed: 0,
                 }
             },
         }
     }
 
+    /// Derive a stable signature for deduplication.
+    ///
+    /// The signature concatenates, in order, the `(kind, canonical)` pairs of
+    /// the non-warning chunks from both `v1_chunks` and `v2_chunks`. Because the
+    /// canonical strings already have variable/module/type names and error codes
+    /// stripped, two failures that are textually different but semantically
+    /// identical hash to the same value and collapse into a single report.
+    fn signature(&self) -> u64 {
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        for chunks in [&self.v1_chunks, &self.v2_chunks] {
+            for chunk in chunks.iter() {
+                if chunk.kind == ResultChunkKind::Warning {
+                    continue;
+                }
+                chunk.kind.hash(&mut hasher);
+                chunk.canonical.hash(&mut hasher);
+            }
+        }
+        hasher.finish()
+    }
+
     fn split_diff_log(log: &str) -> (Vec<String>, Vec<String>) {
         let mut left = vec![];
         let mut right = vec![];
@@ -160,38 +202,83 @@ impl TransactionalResult {
     }
 }
 
+/// One entry of an aligned V1/V2 diagnostic diff: a chunk that matched on both
+/// sides, or one that appears only in V1 or only in V2.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChunkAlignment {
+    Matched(ResultChunk),
+    V1Only(ResultChunk),
+    V2Only(ResultChunk),
+}
+
 impl ResultStatus {
     pub fn check_chunks(v1_chunks: &[ResultChunk], v2_chunks: &[ResultChunk]) -> Self {
         if v1_chunks.is_empty() && v2_chunks.is_empty() {
             return Self::Success;
         }
-        if v1_chunks.len() != v2_chunks.len() {
-            return Self::Failure;
+        // `log_to_chunck` already strips every `Warning` chunk before this runs,
+        // so any chunk reaching the alignment is non-warning by construction:
+        // an unmatched chunk on either side is itself a divergence (this
+        // subsumes an unmatched `Bug`/`Panic`).
+        let unmatched = Self::align_chunks(v1_chunks, v2_chunks)
+            .into_iter()
+            .any(|entry| !matches!(entry, ChunkAlignment::Matched(_)));
+        if unmatched {
+            Self::Failure
+        } else {
+            Self::Success
         }
-        for i in 0..v1_chunks.len() {
-            if v2_chunks[i].kind == ResultChunkKind::Bug {
-                return Self::Failure;
+    }
+
+    /// Align the two canonical-chunk sequences via a longest-common-subsequence
+    /// diff, classifying each chunk as matched, v1-only, or v2-only. Rows of the
+    /// DP table index `v1_chunks`, columns index `v2_chunks`.
+    pub fn align_chunks(v1_chunks: &[ResultChunk], v2_chunks: &[ResultChunk]) -> Vec<ChunkAlignment> {
+        let n = v1_chunks.len();
+        let m = v2_chunks.len();
+        let mut dp = vec![vec![0usize; m + 1]; n + 1];
+        for i in 1..=n {
+            for j in 1..=m {
+                dp[i][j] = if v1_chunks[i - 1].canonical == v2_chunks[j - 1].canonical {
+                    dp[i - 1][j - 1] + 1
+                } else {
+                    dp[i - 1][j].max(dp[i][j - 1])
+                };
             }
-            if v1_chunks[i].canonical != v2_chunks[i].canonical {
-                return Self::Failure;
+        }
+
+        let mut aligned = vec![];
+        let (mut i, mut j) = (n, m);
+        while i > 0 && j > 0 {
+            if v1_chunks[i - 1].canonical == v2_chunks[j - 1].canonical {
+                aligned.push(ChunkAlignment::Matched(v1_chunks[i - 1].clone()));
+                i -= 1;
+                j -= 1;
+            } else if dp[i - 1][j] >= dp[i][j - 1] {
+                aligned.push(ChunkAlignment::V1Only(v1_chunks[i - 1].clone()));
+                i -= 1;
+            } else {
+                aligned.push(ChunkAlignment::V2Only(v2_chunks[j - 1].clone()));
+                j -= 1;
             }
         }
-        Self::Success
+        while i > 0 {
+            aligned.push(ChunkAlignment::V1Only(v1_chunks[i - 1].clone()));
+            i -= 1;
+        }
+        while j > 0 {
+            aligned.push(ChunkAlignment::V2Only(v2_chunks[j - 1].clone()));
+            j -= 1;
+        }
+        aligned.reverse();
+        aligned
     }
 }
 
-static LOCAL_PAT: Lazy<Regex> = Lazy::new(|| Regex::new(r"local\s+`[^`]+`").unwrap());
-
-static MODULE_PAT: Lazy<Regex> = Lazy::new(|| Regex::new(r"module\s+'[^']+'").unwrap());
-
-static TYPE_PAT: Lazy<Regex> = Lazy::new(|| Regex::new(r"type\s+`[^`]+`").unwrap());
-
-static SOME_PAT: Lazy<Regex> = Lazy::new(|| Regex::new(r"Some\([^\)]+\)").unwrap());
-
 static ERROR_CODE_PAT: Lazy<Regex> = Lazy::new(|| Regex::new(r"`([^`]*)`").unwrap());
 
 impl ResultChunk {
-    fn log_to_chunck(log: Vec<String>) -> Vec<ResultChunk> {
+    fn log_to_chunck(log: Vec<String>, rules: &CanonicalizationRules) -> Vec<ResultChunk> {
         let mut chunks = vec![];
         for line in log.into_iter() {
             if let Some(kind) = ResultChunkKind::try_from_str(&line) {
@@ -209,11 +296,11 @@ impl ResultChunk {
         chunks.retain(|e| e.kind != ResultChunkKind::Warning);
         chunks
             .iter_mut()
-            .for_each(|e| e.canonical = e.get_canonicalized_msg());
+            .for_each(|e| e.canonical = e.get_canonicalized_msg(rules));
         chunks
     }
 
-    fn get_canonicalized_msg(&self) -> String {
+    fn get_canonicalized_msg(&self, rules: &CanonicalizationRules) -> String {
         let top = match self.kind {
             ResultChunkKind::VMError => self.lines.get(1).unwrap().trim(),
             _ => self.lines.get(0).unwrap(),
@@ -231,65 +318,50 @@ impl ResultChunk {
             }
         }
 
-        if top.contains("mutable ownership violated")
-            || top.contains("which is still mutably borrowed")
-        {
-            return "...cannot copy while mutably borrowed...".to_string();
-        }
-
-        if top.contains("cannot extract resource") || top.contains("function acquires global") {
-            return "...cannot acquire...".to_string();
-        }
-
-        if top.contains("cannot infer type")
-            || top.contains("unable to infer instantiation of type")
-        {
-            return "...cannot infer type...".to_string();
-        }
-        let replaced = LOCAL_PAT.replace_all(&top, "[some variable]").to_string();
-        let replaced = MODULE_PAT
-            .replace_all(&replaced, "[some module]")
-            .to_string();
-        let replaced = TYPE_PAT.replace_all(&replaced, "[some type]").to_string();
-        let replaced = SOME_PAT.replace_all(&replaced, "[some value]").to_string();
-        replaced
+        rules.apply(&top)
     }
 }
 
 impl Display for TransactionalResult {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         writeln!(f, "Status: {:?}", self.status)?;
+        writeln!(f, "Seed: {}", self.seed)?;
         writeln!(f, "Duration: {:?}", self.duration)?;
-        writeln!(f, "\nV1 output:")?;
-        for chunk in self.v1_chunks.iter() {
-            writeln!(f, "{}", chunk.canonical)?;
-        }
-        writeln!(f, "\nV2 output:")?;
-        for chunk in self.v2_chunks.iter() {
-            writeln!(f, "{}", chunk.canonical)?;
+        writeln!(f, "\nAligned diff (= common, - v1-only, + v2-only):")?;
+        for entry in ResultStatus::align_chunks(&self.v1_chunks, &self.v2_chunks) {
+            match entry {
+                ChunkAlignment::Matched(chunk) => writeln!(f, "= {}", chunk.canonical)?,
+                ChunkAlignment::V1Only(chunk) => writeln!(f, "- {}", chunk.canonical)?,
+                ChunkAlignment::V2Only(chunk) => writeln!(f, "+ {}", chunk.canonical)?,
+            }
         }
         Ok(())
     }
 }
 
-impl Executor<TransactionalInput, TransactionalResult> for TransactionalRunner {
-    fn empty_executor() -> Self {
-        Self {
-            saved_results: BTreeSet::new(),
-        }
-    }
-
-    fn execute_one(&self, input: &TransactionalInput) -> TransactionalResult {
-        let (path, dir) = input.get_file_path();
-
-        let experiments = input.config.to_expriments();
+impl TransactionalRunner {
+    /// Run a single input, catching any panic from the VM harness. The global
+    /// panic hook is assumed to already be installed by the caller, so this
+    /// helper can be shared between the synchronous `execute_one` path and the
+    /// parallel `run_many` path without swapping the hook per test.
+    fn execute_catching(input: &TransactionalInput) -> TransactionalResult {
         let vm_test_config = TestRunConfig::ComparisonV1V2 {
             language_version: LanguageVersion::V2_0,
-            v2_experiments: experiments,
+            v2_experiments: input.config.to_expriments(),
         };
+        Self::run_with_config(&input.code, vm_test_config, canonicalize::default_rules())
+    }
+
+    /// Run one source string under an explicit [`TestRunConfig`], catching any
+    /// panic from the VM harness. The global panic hook is assumed to already be
+    /// installed by the caller.
+    fn run_with_config(
+        code: &str,
+        vm_test_config: TestRunConfig,
+        rules: &CanonicalizationRules,
+    ) -> TransactionalResult {
+        let (path, dir) = create_tmp_move_file(code, None);
 
-        let prev_hook = panic::take_hook();
-        panic::set_hook(Box::new(|_| {}));
         let start = Instant::now();
         let result = match panic::catch_unwind(|| {
             vm_test_harness::run_test_with_config_and_exp_suffix(vm_test_config, &path, &None)
@@ -298,20 +370,236 @@ impl Executor<TransactionalInput, TransactionalResult> for TransactionalRunner {
             Err(e) => Err(anyhow::anyhow!("{:?}", e).into()),
         };
         let duration = start.elapsed();
-        panic::set_hook(prev_hook);
 
-        let output = TransactionalResult::from_run_result(&result, duration);
+        let output = TransactionalResult::from_run_result(&result, duration, rules);
         dir.close().unwrap();
         output
     }
 
+    /// Run one source string under a single named `manifest` profile, returning
+    /// its V1-vs-profile [`TransactionalResult`]. Used by [`Self::run_matrix`] to
+    /// get each profile's own diagnostics before cross-comparing two profiles.
+    fn run_profile(
+        code: &str,
+        manifest: &Manifest,
+        name: &str,
+        rules: &CanonicalizationRules,
+    ) -> Option<TransactionalResult> {
+        let profile = manifest.profile(name).or_else(|| {
+            error!("unknown profile in matrix: {:?}", name);
+            None
+        })?;
+        let vm_test_config = TestRunConfig::ComparisonV1V2 {
+            language_version: profile.to_language_version(),
+            v2_experiments: profile.to_experiments(),
+        };
+        Some(Self::run_with_config(code, vm_test_config, rules))
+    }
+
+    /// Run one source string under every profile pair in `manifest.matrix`,
+    /// cross-comparing the two profiles' own diagnostics (not just each against
+    /// V1) so divergences between optimization levels or language versions of V2
+    /// become visible. Emits one [`TransactionalResult`] per pair, whose
+    /// `v1_chunks`/`v2_chunks` hold the first/second profile's diagnostics and
+    /// whose `status` is [`ResultStatus::check_chunks`] between them. Canonicalization
+    /// rules come from `manifest`'s `[canonicalization]` section when present (see
+    /// [`Manifest::canonicalization_rules`]), falling back to the built-in table
+    /// otherwise. Results are funnelled through the dedup `save_result` path.
+    pub fn run_matrix(&mut self, code: &str, manifest: &Manifest) -> Vec<TransactionalResult> {
+        // Resolved before the output-suppressing panic hook below is installed,
+        // so a malformed `[canonicalization]` regex still panics with its actual
+        // error message instead of being swallowed by the hook.
+        let rules = manifest
+            .canonicalization_rules()
+            .unwrap_or_else(|e| panic!("invalid canonicalization rules in manifest: {e}"));
+
+        let prev_hook = panic::take_hook();
+        panic::set_hook(Box::new(|_| {}));
+
+        let mut results = vec![];
+        for [name_a, name_b] in manifest.matrix.iter() {
+            let (Some(result_a), Some(result_b)) = (
+                Self::run_profile(code, manifest, name_a, &rules),
+                Self::run_profile(code, manifest, name_b, &rules),
+            ) else {
+                continue;
+            };
+
+            let status = ResultStatus::check_chunks(&result_a.v2_chunks, &result_b.v2_chunks);
+            let result = TransactionalResult {
+                log: format!(
+                    "--- {name_a} ---\n{}\n--- {name_b} ---\n{}",
+                    result_a.log, result_b.log
+                ),
+                status,
+                v1_chunks: result_a.v2_chunks,
+                v2_chunks: result_b.v2_chunks,
+                duration: result_a.duration + result_b.duration,
+                seed: 0,
+            };
+            self.save_result(result.clone());
+            results.push(result);
+        }
+
+        panic::set_hook(prev_hook);
+        results
+    }
+
+    /// Execute a corpus of inputs concurrently on a bounded worker pool sized to
+    /// the available parallelism, funnelling every result through the dedup
+    /// `save_result` path so the shared `saved_results` set stays consistent.
+    ///
+    /// Output is returned in input order (see [`Self::run_many_with_options`] to
+    /// opt out of deterministic ordering).
+    pub fn run_many(&mut self, inputs: Vec<TransactionalInput>) -> Vec<TransactionalResult> {
+        self.run_many_with_options(inputs, true)
+    }
+
+    /// Like [`Self::run_many`], but `preserve_order` controls whether results are
+    /// reordered back into input order. When `false`, results are returned in
+    /// completion order, which is non-deterministic across runs.
+    pub fn run_many_with_options(
+        &mut self,
+        inputs: Vec<TransactionalInput>,
+        preserve_order: bool,
+    ) -> Vec<TransactionalResult> {
+        self.run_many_seeded(inputs, preserve_order, 0)
+    }
+
+    /// Like [`Self::run_many_with_options`], but stamps every result with `seed`
+    /// *before* it is funnelled through `save_result`, so the copies kept in the
+    /// dedup set carry the same seed as the copies returned to the caller. Used
+    /// by [`CorpusRunner::run`] so the seed embedded for replay is accurate
+    /// everywhere, not just in the returned vec.
+    pub fn run_many_seeded(
+        &mut self,
+        inputs: Vec<TransactionalInput>,
+        preserve_order: bool,
+        seed: u64,
+    ) -> Vec<TransactionalResult> {
+        if inputs.is_empty() {
+            return vec![];
+        }
+
+        let workers = std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1)
+            .min(inputs.len());
+
+        // Install the silencing hook exactly once for the whole run. The harness
+        // can panic and per-test hook swapping would race across workers.
+        let prev_hook = panic::take_hook();
+        panic::set_hook(Box::new(|_| {}));
+
+        let next = AtomicUsize::new(0);
+        let inputs_ref = &inputs;
+        let save_lock = Mutex::new(&mut *self);
+        let collected: Mutex<Vec<(usize, TransactionalResult)>> =
+            Mutex::new(Vec::with_capacity(inputs.len()));
+
+        std::thread::scope(|scope| {
+            for _ in 0..workers {
+                scope.spawn(|| loop {
+                    let idx = next.fetch_add(1, Ordering::Relaxed);
+                    if idx >= inputs_ref.len() {
+                        break;
+                    }
+                    let mut result = Self::execute_catching(&inputs_ref[idx]);
+                    result.seed = seed;
+                    save_lock.lock().unwrap().save_result(result.clone());
+                    collected.lock().unwrap().push((idx, result));
+                });
+            }
+        });
+
+        panic::set_hook(prev_hook);
+
+        let mut collected = collected.into_inner().unwrap();
+        if preserve_order {
+            collected.sort_by_key(|(idx, _)| *idx);
+        }
+        collected.into_iter().map(|(_, result)| result).collect()
+    }
+}
+
+/// Runs a corpus of transactional inputs in a seed-determined order so that a
+/// surprising V1/V2 divergence can be replayed exactly. The seed drives a small
+/// seedable PRNG that shuffles the execution order; printing the seed on startup
+/// and feeding it back on the next run reproduces the same ordering.
+pub struct CorpusRunner {
+    seed: u64,
+    runner: TransactionalRunner,
+}
+
+impl CorpusRunner {
+    /// Create a runner that shuffles its corpus with the given seed. Pass a seed
+    /// reported by a previous run to reproduce its ordering.
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            runner: TransactionalRunner::empty_executor(),
+        }
+    }
+
+    /// The seed used to order this run.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Build the corpus from every `.move` file directly under `dir`.
+    pub fn inputs_from_dir(dir: &PathBuf, config: &CompilerSetting) -> Vec<TransactionalInput> {
+        let mut inputs = vec![];
+        for entry in std::fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().and_then(|e| e.to_str()) == Some("move") {
+                inputs.push(TransactionalInput::new_from_file(path, config));
+            }
+        }
+        inputs
+    }
+
+    /// Shuffle `inputs` into the seed-determined execution order.
+    pub fn shuffled(&self, mut inputs: Vec<TransactionalInput>) -> Vec<TransactionalInput> {
+        let mut rng = SmallRng::seed_from_u64(self.seed);
+        inputs.shuffle(&mut rng);
+        inputs
+    }
+
+    /// Shuffle and run the corpus, tagging each result with the run seed so the
+    /// failure report carries everything needed to replay it.
+    pub fn run(&mut self, inputs: Vec<TransactionalInput>) -> Vec<TransactionalResult> {
+        // Printed unconditionally (not just `info!`-logged) so the seed needed to
+        // replay a surprising divergence is visible even without logging set up.
+        println!("running corpus of {} inputs with seed {}", inputs.len(), self.seed);
+        info!("running corpus of {} inputs with seed {}", inputs.len(), self.seed);
+        let inputs = self.shuffled(inputs);
+        self.runner.run_many_seeded(inputs, true, self.seed)
+    }
+}
+
+impl Executor<TransactionalInput, TransactionalResult> for TransactionalRunner {
+    fn empty_executor() -> Self {
+        Self {
+            saved_results: BTreeSet::new(),
+            saved_signatures: BTreeSet::new(),
+        }
+    }
+
+    fn execute_one(&self, input: &TransactionalInput) -> TransactionalResult {
+        let prev_hook = panic::take_hook();
+        panic::set_hook(Box::new(|_| {}));
+        let output = Self::execute_catching(input);
+        panic::set_hook(prev_hook);
+        output
+    }
+
     fn save_result(&mut self, result: TransactionalResult) {
-        unimplemented!()
+        self.saved_signatures.insert(result.signature());
+        self.saved_results.insert(result);
     }
 
     fn should_ignore(&self, result: &TransactionalResult) -> bool {
-        // TODO: implement this
-        return false;
+        self.saved_signatures.contains(&result.signature())
     }
 
     fn is_bug(&self, result: &TransactionalResult) -> bool {